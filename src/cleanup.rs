@@ -0,0 +1,117 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// What to do with an original file once it has been compressed
+/// successfully.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CleanupBehavior {
+    /// Leave the original file in place.
+    #[default]
+    Keep,
+    /// Permanently remove the original file.
+    Delete,
+    /// Move the original file into an archive directory instead of
+    /// removing it.
+    Archive,
+}
+
+impl FromStr for CleanupBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(CleanupBehavior::Keep),
+            "delete" => Ok(CleanupBehavior::Delete),
+            "archive" => Ok(CleanupBehavior::Archive),
+            other => Err(format!("unknown cleanup behavior: {other}")),
+        }
+    }
+}
+
+/// Settings that control what happens to an original file after it has
+/// been compressed.
+pub struct CleanupOptions {
+    pub behavior: CleanupBehavior,
+    pub archive_dir: Option<PathBuf>,
+    pub remove_empty_directories: bool,
+    /// Whether archived files should mirror their path relative to
+    /// `input_dir` under `archive_dir`, matching the same setting used
+    /// for compressed output.
+    pub keep_file_structure: bool,
+}
+
+/// Cleans up `successful_files` (files under `input_dir` that ffmpeg
+/// compressed successfully) according to `options`.
+/// Files that failed to compress are never touched.
+pub fn cleanup_originals(
+    options: &CleanupOptions,
+    input_dir: &Path,
+    successful_files: &[PathBuf],
+) -> io::Result<()> {
+    match options.behavior {
+        CleanupBehavior::Keep => Ok(()),
+        CleanupBehavior::Delete => {
+            for video_file in successful_files {
+                fs::remove_file(video_file)?;
+                println!("Deleted file: {:?}", video_file);
+            }
+            if options.remove_empty_directories {
+                remove_empty_directories(input_dir)?;
+            }
+            Ok(())
+        }
+        CleanupBehavior::Archive => {
+            let archive_dir = options
+                .archive_dir
+                .as_deref()
+                .expect("archive cleanup requires an archive directory");
+
+            for video_file in successful_files {
+                let archived_file = if options.keep_file_structure {
+                    let relative = video_file.strip_prefix(input_dir).unwrap_or(video_file);
+                    archive_dir.join(relative)
+                } else {
+                    archive_dir.join(video_file.file_name().unwrap())
+                };
+                if let Some(parent) = archived_file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(video_file, &archived_file)?;
+                println!("Archived file: {:?} -> {:?}", video_file, archived_file);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Removes every empty subdirectory under `input_dir`, walking
+/// bottom-up so that emptying a child can cause its parent to become
+/// empty too. `input_dir` itself is never removed.
+fn remove_empty_directories(input_dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(input_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            prune_if_empty(&entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Recursively removes `dir` (and any of its subdirectories) if, after
+/// pruning, it is left empty.
+fn prune_if_empty(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            prune_if_empty(&entry.path())?;
+        }
+    }
+
+    if fs::read_dir(dir)?.next().is_none() {
+        fs::remove_dir(dir)?;
+    }
+
+    Ok(())
+}
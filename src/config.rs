@@ -0,0 +1,111 @@
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Top-level configuration loaded from a `--config <path.toml>` file.
+///
+/// Every field is optional so that a config file can specify only the
+/// settings it cares about; anything left unset falls back to the CLI
+/// defaults in `main`.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub files: FilesConfig,
+    #[serde(default)]
+    pub ffmpeg: FfmpegConfig,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FilesConfig {
+    pub input_path: Option<PathBuf>,
+    pub output_path: Option<PathBuf>,
+    /// File extensions (without the leading dot) that are fed to ffmpeg.
+    /// Everything else discovered by `WalkDir` is skipped.
+    #[serde(default = "default_include")]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub keep_file_structure: bool,
+    #[serde(default)]
+    pub cleanup: CleanupConfig,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        FilesConfig {
+            input_path: None,
+            output_path: None,
+            include: default_include(),
+            keep_file_structure: false,
+            cleanup: CleanupConfig::default(),
+        }
+    }
+}
+
+fn default_include() -> Vec<String> {
+    vec!["mp4".to_string(), "mkv".to_string(), "avi".to_string()]
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CleanupConfig {
+    pub original_cleanup_behavior: Option<String>,
+    pub archive_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub remove_empty_directories: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FfmpegConfig {
+    pub binary: Option<String>,
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    pub crf: Option<u32>,
+    pub preset: Option<String>,
+    pub codec: Option<String>,
+}
+
+impl Config {
+    /// Loads and deserializes a TOML config file from `path`.
+    pub fn load(path: &Path) -> io::Result<Config> {
+        let contents = fs::read_to_string(path)?;
+        toml::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+/// Returns `true` if `path`'s extension (case-insensitively) is present in
+/// `include`. Files without an extension are never matched.
+pub fn has_allowed_extension(path: &Path, include: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            include
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_extension_case_insensitively() {
+        let include = default_include();
+        assert!(has_allowed_extension(Path::new("video.MP4"), &include));
+        assert!(has_allowed_extension(Path::new("video.mkv"), &include));
+    }
+
+    #[test]
+    fn rejects_extension_not_in_include_list() {
+        let include = default_include();
+        assert!(!has_allowed_extension(Path::new("video.mov"), &include));
+    }
+
+    #[test]
+    fn rejects_path_with_no_extension() {
+        let include = default_include();
+        assert!(!has_allowed_extension(Path::new("video"), &include));
+    }
+}
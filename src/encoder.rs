@@ -0,0 +1,162 @@
+use std::fmt;
+use std::io;
+use std::process::Command as ProcessCommand;
+use std::str::FromStr;
+
+/// A selectable ffmpeg video encoder. Each variant knows how to render
+/// its own preset and quality flags, since the flag names and value
+/// ranges differ between encoders (e.g. NVENC's `-cq` vs x264/x265's
+/// `-crf`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoder {
+    HevcNvenc,
+    Libx265,
+    Libx264,
+    SvtAv1,
+    Av1Nvenc,
+}
+
+impl Encoder {
+    /// The ffmpeg `-vcodec` value for this encoder.
+    pub fn codec_name(self) -> &'static str {
+        match self {
+            Encoder::HevcNvenc => "hevc_nvenc",
+            Encoder::Libx265 => "libx265",
+            Encoder::Libx264 => "libx264",
+            Encoder::SvtAv1 => "libsvtav1",
+            Encoder::Av1Nvenc => "av1_nvenc",
+        }
+    }
+
+    /// Whether this encoder requires an NVIDIA GPU.
+    pub fn is_nvenc(self) -> bool {
+        matches!(self, Encoder::HevcNvenc | Encoder::Av1Nvenc)
+    }
+
+    /// The software encoder to fall back to when this is an NVENC
+    /// encoder that isn't available on the current machine.
+    pub fn software_fallback(self) -> Option<Encoder> {
+        match self {
+            Encoder::HevcNvenc => Some(Encoder::Libx265),
+            Encoder::Av1Nvenc => Some(Encoder::SvtAv1),
+            Encoder::Libx265 | Encoder::Libx264 | Encoder::SvtAv1 => None,
+        }
+    }
+
+    /// Builds the `-preset`/`-cpu-used`-style argument pair for `preset`.
+    /// `svt-av1` takes a numeric speed value via `-preset` rather than a
+    /// named preset, but we pass the configured value through either way
+    /// and let the user pick a value that matches their encoder.
+    pub fn preset_args(self, preset: &str) -> Vec<String> {
+        vec!["-preset".to_string(), preset.to_string()]
+    }
+
+    /// Builds the quality argument pair for `crf`, mapping to the flag
+    /// each encoder actually understands (NVENC uses `-cq`, the software
+    /// encoders use `-crf`).
+    pub fn quality_args(self, crf: &str) -> Vec<String> {
+        let flag = if self.is_nvenc() { "-cq" } else { "-crf" };
+        vec![flag.to_string(), crf.to_string()]
+    }
+}
+
+impl fmt::Display for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.codec_name())
+    }
+}
+
+impl FromStr for Encoder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hevc_nvenc" => Ok(Encoder::HevcNvenc),
+            "libx265" => Ok(Encoder::Libx265),
+            "libx264" => Ok(Encoder::Libx264),
+            "libsvtav1" | "svt_av1" => Ok(Encoder::SvtAv1),
+            "av1_nvenc" => Ok(Encoder::Av1Nvenc),
+            other => Err(format!("unknown encoder: {other}")),
+        }
+    }
+}
+
+/// Runs `ffmpeg -hide_banner -encoders` and returns the set of encoder
+/// names ffmpeg reports as available.
+fn list_available_encoders(ffmpeg_binary: &str) -> io::Result<Vec<String>> {
+    let output = ProcessCommand::new(ffmpeg_binary)
+        .args(["-hide_banner", "-encoders"])
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        // Encoder lines look like " V..... hevc_nvenc  NVIDIA NVENC hevc encoder"
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|name| name.to_string())
+        .collect())
+}
+
+/// Probes ffmpeg for the requested encoder's availability, falling back
+/// to its software equivalent (with a warning) if it's an NVENC encoder
+/// that isn't present.
+pub fn resolve_encoder(ffmpeg_binary: &str, requested: Encoder) -> io::Result<Encoder> {
+    let available = list_available_encoders(ffmpeg_binary)?;
+    Ok(pick_encoder(&available, requested))
+}
+
+/// The pure fallback decision: given the set of encoder names ffmpeg
+/// reports as available, decides which encoder to actually use. Split
+/// out from `resolve_encoder` so this decision table can be tested
+/// without shelling out to ffmpeg.
+fn pick_encoder(available: &[String], requested: Encoder) -> Encoder {
+    if available.iter().any(|name| name == requested.codec_name()) {
+        return requested;
+    }
+
+    match requested.software_fallback() {
+        Some(fallback) => {
+            eprintln!(
+                "Warning: encoder '{}' is not available, falling back to '{}'.",
+                requested.codec_name(),
+                fallback.codec_name()
+            );
+            fallback
+        }
+        None => requested,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_requested_encoder_when_available() {
+        let available = vec!["hevc_nvenc".to_string(), "libx264".to_string()];
+        assert_eq!(
+            pick_encoder(&available, Encoder::HevcNvenc),
+            Encoder::HevcNvenc
+        );
+    }
+
+    #[test]
+    fn falls_back_to_software_when_nvenc_unavailable() {
+        let available = vec!["libx264".to_string(), "libx265".to_string()];
+        assert_eq!(
+            pick_encoder(&available, Encoder::HevcNvenc),
+            Encoder::Libx265
+        );
+    }
+
+    #[test]
+    fn keeps_requested_encoder_when_no_fallback_exists() {
+        // libx264 has no software_fallback, so even if it's reported
+        // missing we still return it as requested rather than erroring.
+        let available = vec!["hevc_nvenc".to_string()];
+        assert_eq!(
+            pick_encoder(&available, Encoder::Libx264),
+            Encoder::Libx264
+        );
+    }
+}
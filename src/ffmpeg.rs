@@ -0,0 +1,86 @@
+use crate::encoder::Encoder;
+use std::path::Path;
+use std::process::Command as ProcessCommand;
+
+/// Builds an ffmpeg invocation for a single file, accumulating
+/// input-side flags (seek, duration) and output-side flags
+/// (frame rate, codec, preset, quality) so optional transforms compose cleanly
+/// instead of being spliced into one literal argument array.
+pub struct FfmpegCommandBuilder {
+    binary: String,
+    input: String,
+    output: String,
+    input_args: Vec<String>,
+    output_args: Vec<String>,
+}
+
+impl FfmpegCommandBuilder {
+    pub fn new(binary: &str, input: &Path, output: &Path) -> Self {
+        FfmpegCommandBuilder {
+            binary: binary.to_string(),
+            input: input.to_str().unwrap().to_string(),
+            output: output.to_str().unwrap().to_string(),
+            input_args: Vec::new(),
+            output_args: Vec::new(),
+        }
+    }
+
+    /// Inserts `-ss <start>` to seek the input before decoding.
+    pub fn start(mut self, start: Option<&str>) -> Self {
+        if let Some(start) = start {
+            self.input_args.push("-ss".to_string());
+            self.input_args.push(start.to_string());
+        }
+        self
+    }
+
+    /// Inserts `-t <duration>` to limit how much of the input is read.
+    pub fn duration(mut self, duration: Option<&str>) -> Self {
+        if let Some(duration) = duration {
+            self.input_args.push("-t".to_string());
+            self.input_args.push(duration.to_string());
+        }
+        self
+    }
+
+    /// Inserts `-r <fps>` to resample the output frame rate.
+    pub fn fps(mut self, fps: Option<u32>) -> Self {
+        if let Some(fps) = fps {
+            self.output_args.push("-r".to_string());
+            self.output_args.push(fps.to_string());
+        }
+        self
+    }
+
+    /// Appends the metadata, codec, preset and quality flags shared by
+    /// every compression run.
+    pub fn codec(mut self, encoder: Encoder, preset: &str, crf: &str) -> Self {
+        self.output_args.push("-movflags".to_string());
+        self.output_args.push("use_metadata_tags".to_string());
+        self.output_args.push("-map_metadata".to_string());
+        self.output_args.push("0".to_string());
+        self.output_args.push("-vcodec".to_string());
+        self.output_args.push(encoder.codec_name().to_string());
+        self.output_args.extend(encoder.preset_args(preset));
+        self.output_args.extend(encoder.quality_args(crf));
+        self.output_args.push("-c:a".to_string());
+        self.output_args.push("copy".to_string());
+        self
+    }
+
+    /// Appends any additional user-configured ffmpeg arguments.
+    pub fn extra_args(mut self, args: &[String]) -> Self {
+        self.output_args.extend(args.iter().cloned());
+        self
+    }
+
+    /// Renders the accumulated flags into a ready-to-run `Command`.
+    pub fn build(self) -> ProcessCommand {
+        let mut command = ProcessCommand::new(&self.binary);
+        command.args(self.input_args);
+        command.args(["-i", &self.input]);
+        command.args(self.output_args);
+        command.arg(&self.output);
+        command
+    }
+}
@@ -1,114 +1,352 @@
+mod cleanup;
+mod config;
+mod encoder;
+mod ffmpeg;
+
 use clap::{Arg, Command};
-use indicatif::{ProgressBar, ProgressStyle};
+use cleanup::{CleanupBehavior, CleanupOptions};
+use config::Config;
+use encoder::Encoder;
+use ffmpeg::FfmpegCommandBuilder;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::VecDeque;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command as ProcessCommand;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use walkdir::WalkDir;
-use std::io::{self, Write};
+use std::io;
 
-fn compress_videos(input_dir: &Path, output_dir: &Path) -> io::Result<()> {
-    // Create the output directory if it doesn't exist
-    if !output_dir.exists() {
-        fs::create_dir_all(output_dir)?;
+/// Fully resolved settings for a run: config file values merged with any
+/// CLI overrides, which always win.
+struct Settings {
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+    include: Vec<String>,
+    keep_file_structure: bool,
+    jobs: usize,
+    watch: bool,
+    interval: u64,
+    ffmpeg_binary: String,
+    preset: String,
+    crf: String,
+    encoder: Encoder,
+    extra_args: Vec<String>,
+    cleanup: CleanupOptions,
+    start: Option<String>,
+    duration: Option<String>,
+    fps: Option<u32>,
+}
+
+impl Settings {
+    fn resolve(matches: &clap::ArgMatches, config: Config) -> io::Result<Settings> {
+        let input_dir = matches
+            .get_one::<PathBuf>("input_directory")
+            .cloned()
+            .or(config.files.input_path)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "input directory must be set via --input or the config file",
+                )
+            })?;
+
+        let output_dir = matches
+            .get_one::<PathBuf>("output_directory")
+            .cloned()
+            .or(config.files.output_path)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "output directory must be set via --output or the config file",
+                )
+            })?;
+
+        let keep_file_structure =
+            matches.get_flag("preserve_structure") || config.files.keep_file_structure;
+
+        let jobs = matches.get_one::<usize>("jobs").copied().unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        });
+
+        let watch = matches.get_flag("watch");
+        let interval = matches.get_one::<u64>("interval").copied().unwrap_or(60);
+
+        let encoder = matches
+            .get_one::<Encoder>("encoder")
+            .copied()
+            .or_else(|| config.ffmpeg.codec.as_deref().and_then(|c| c.parse().ok()))
+            .unwrap_or(Encoder::HevcNvenc);
+
+        let cleanup = CleanupOptions {
+            behavior: matches
+                .get_one::<CleanupBehavior>("cleanup")
+                .copied()
+                .or_else(|| {
+                    config
+                        .files
+                        .cleanup
+                        .original_cleanup_behavior
+                        .as_deref()
+                        .and_then(|b| b.parse().ok())
+                })
+                .unwrap_or_default(),
+            archive_dir: matches
+                .get_one::<PathBuf>("archive_dir")
+                .cloned()
+                .or(config.files.cleanup.archive_directory),
+            remove_empty_directories: matches.get_flag("remove_empty_dirs")
+                || config.files.cleanup.remove_empty_directories,
+            keep_file_structure,
+        };
+
+        if cleanup.behavior == CleanupBehavior::Archive && cleanup.archive_dir.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--cleanup archive requires --archive-dir or config files.cleanup.archive_directory",
+            ));
+        }
+
+        Ok(Settings {
+            input_dir,
+            output_dir,
+            include: config.files.include,
+            keep_file_structure,
+            jobs,
+            watch,
+            interval,
+            ffmpeg_binary: config.ffmpeg.binary.unwrap_or_else(|| "ffmpeg".to_string()),
+            preset: config.ffmpeg.preset.unwrap_or_else(|| "fast".to_string()),
+            crf: config
+                .ffmpeg
+                .crf
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "28".to_string()),
+            encoder,
+            extra_args: config.ffmpeg.extra_args,
+            cleanup,
+            start: matches.get_one::<String>("start").cloned(),
+            duration: matches.get_one::<String>("duration").cloned(),
+            fps: matches.get_one::<u32>("fps").copied(),
+        })
     }
+}
 
-    // Collect all video files in the input directory
-    let video_files: Vec<PathBuf> = WalkDir::new(input_dir)
+/// Walks `dir` and returns every file whose extension is in `include`.
+fn discover_video_files(dir: &Path, include: &[String]) -> Vec<PathBuf> {
+    WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
         .map(|e| e.into_path())
-        .collect();
+        .filter(|path| config::has_allowed_extension(path, include))
+        .collect()
+}
+
+/// Computes where a compressed copy of `video_file` should be written.
+/// Purely computes a path — it never touches the filesystem, so it's
+/// safe to call from read-only checks like `needs_processing`.
+///
+/// With `keep_file_structure` enabled, `video_file`'s path relative to
+/// `input_dir` is recreated under `output_dir` (e.g. `in/foo/bar.mp4 ->
+/// out/foo/bar.mp4`); otherwise every file is flattened into `output_dir`.
+fn output_path_for(settings: &Settings, video_file: &Path) -> PathBuf {
+    if settings.keep_file_structure {
+        let relative = video_file
+            .strip_prefix(&settings.input_dir)
+            .unwrap_or(video_file);
+        settings.output_dir.join(relative)
+    } else {
+        settings.output_dir.join(video_file.file_name().unwrap())
+    }
+}
+
+/// Compresses a single file, writing its ffmpeg output to a log file next
+/// to the output, and reports progress on `job_pb`. Returns whether
+/// ffmpeg exited successfully.
+fn compress_one_video(settings: &Settings, video_file: &Path, job_pb: &ProgressBar) -> io::Result<bool> {
+    let output_file = output_path_for(settings, video_file);
+    if let Some(parent) = output_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let log_file = output_file.with_extension("log");
+
+    job_pb.set_message(video_file.display().to_string());
+
+    let ffmpeg_status = FfmpegCommandBuilder::new(&settings.ffmpeg_binary, video_file, &output_file)
+        .start(settings.start.as_deref())
+        .duration(settings.duration.as_deref())
+        .fps(settings.fps)
+        .codec(settings.encoder, &settings.preset, &settings.crf)
+        .extra_args(&settings.extra_args)
+        .build()
+        .stdout(fs::File::create(&log_file)?) // Redirect stdout to log file
+        .stderr(fs::File::create(&log_file)?) // Redirect stderr to log file
+        .status()?;
+
+    Ok(ffmpeg_status.success())
+}
+
+/// Compresses every file in `video_files` using up to `settings.jobs`
+/// concurrent ffmpeg processes. Each worker gets its own progress bar in
+/// a shared `MultiProgress`, alongside one overall completed/failed
+/// counter. Returns the subset of `video_files` that compressed
+/// successfully, so callers can act only on those (e.g. cleanup).
+fn compress_videos(settings: &Settings, video_files: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    // Create the output directory if it doesn't exist
+    if !settings.output_dir.exists() {
+        fs::create_dir_all(&settings.output_dir)?;
+    }
 
     // Abort if no video files are found
     if video_files.is_empty() {
         eprintln!("No video files found in the input directory.");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    // Initialize the progress bar
-    let pb = ProgressBar::new(video_files.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
+    let job_style = ProgressStyle::default_spinner()
+        .template("{spinner:.green} worker {prefix}: {wide_msg}")
+        .unwrap();
+    let overall_style = ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})")
+        .unwrap()
+        .progress_chars("#>-");
+
+    let multi = MultiProgress::new();
+    let overall_pb = multi.add(ProgressBar::new(video_files.len() as u64));
+    overall_pb.set_style(overall_style);
+
+    let num_workers = settings.jobs.max(1).min(video_files.len());
+    let queue: Mutex<VecDeque<&PathBuf>> = Mutex::new(video_files.iter().collect());
+    let failed = AtomicUsize::new(0);
+    let succeeded: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        for worker_id in 0..num_workers {
+            let queue = &queue;
+            let failed = &failed;
+            let succeeded = &succeeded;
+            let overall_pb = &overall_pb;
+            let multi = &multi;
+            let job_style = job_style.clone();
+
+            scope.spawn(move || {
+                let job_pb = multi.add(ProgressBar::new_spinner());
+                job_pb.set_style(job_style);
+                job_pb.set_prefix(worker_id.to_string());
+                job_pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+                loop {
+                    let video_file = match queue.lock().unwrap().pop_front() {
+                        Some(file) => file,
+                        None => break,
+                    };
 
-    for video_file in video_files {
-        let output_file = output_dir.join(video_file.file_name().unwrap());
-        let log_file = output_file.with_extension("log");
-
-        // Compress the video using ffmpeg with hevc_nvenc
-        let ffmpeg_status = ProcessCommand::new("ffmpeg")
-            .args([
-                "-i",
-                video_file.to_str().unwrap(),
-                "-movflags",
-                "use_metadata_tags",
-                "-map_metadata",
-                "0",
-                "-vcodec",
-                "hevc_nvenc",
-                "-preset",
-                "fast",
-                "-crf",
-                "28",
-                "-c:a",
-                "copy",
-                output_file.to_str().unwrap(),
-            ])
-            .stdout(fs::File::create(&log_file)?) // Redirect stdout to log file
-            .stderr(fs::File::create(&log_file)?) // Redirect stderr to log file
-            .status()?;
-
-        if ffmpeg_status.success() {
-            pb.inc(1); // Update the progress bar
-        } else {
-            eprintln!("Failed to process file: {:?}", video_file);
+                    match compress_one_video(settings, video_file, &job_pb) {
+                        Ok(true) => {
+                            succeeded.lock().unwrap().push(video_file.clone());
+                        }
+                        Ok(false) => {
+                            eprintln!("Failed to process file: {:?}", video_file);
+                            failed.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to process file {:?}: {err}", video_file);
+                            failed.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+
+                    overall_pb.inc(1);
+                }
+
+                job_pb.finish_and_clear();
+            });
         }
-    }
+    });
 
-    pb.finish_with_message("Video compression completed.");
-    Ok(())
+    let failed = failed.load(Ordering::Relaxed);
+    overall_pb.finish_with_message(format!(
+        "Video compression completed: {} succeeded, {} failed.",
+        video_files.len() - failed,
+        failed
+    ));
+    Ok(succeeded.into_inner().unwrap())
 }
 
-fn delete_original_files(video_files: Vec<PathBuf>) -> io::Result<()> {
-    // If there's no video_files to delete, return early.
-    if video_files.is_empty() {
-        return Ok(());
+/// Returns `true` if `video_file` has no corresponding output yet, or if
+/// its output is older than the source (i.e. the source changed since it
+/// was last compressed).
+fn needs_processing(settings: &Settings, video_file: &Path) -> io::Result<bool> {
+    let output_file = output_path_for(settings, video_file);
+    if !output_file.exists() {
+        return Ok(true);
     }
 
-    print!("Do you want to delete the original files? (Y/N): ");
-    io::stdout().flush()?;
+    let source_modified = video_file.metadata()?.modified()?;
+    let output_modified = output_file.metadata()?.modified()?;
+    Ok(source_modified > output_modified)
+}
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
+/// Compresses `video_files` and hands the ones that succeeded to
+/// `cleanup::cleanup_originals`; files that failed to compress are left
+/// untouched. Returns the successfully compressed files.
+fn process_files(settings: &Settings, video_files: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let successful_files = compress_videos(settings, video_files)?;
+    cleanup::cleanup_originals(&settings.cleanup, &settings.input_dir, &successful_files)?;
+    Ok(successful_files)
+}
+
+/// Runs a single discover-and-compress pass over every video file found
+/// under `input_dir`, regardless of whether an output already exists.
+fn run_once(settings: &Settings) -> io::Result<Vec<PathBuf>> {
+    let video_files = discover_video_files(&settings.input_dir, &settings.include);
+    process_files(settings, &video_files)
+}
+
+/// Runs forever, sleeping `settings.interval` seconds between passes.
+/// Unlike `run_once`, each pass only compresses files whose output is
+/// missing or stale, so the same input isn't re-encoded every cycle.
+/// Intended for unattended use: directories that keep receiving new
+/// videos over time.
+fn watch(settings: &Settings) -> io::Result<()> {
+    println!(
+        "Watching {:?} every {}s (Ctrl+C to stop)...",
+        settings.input_dir, settings.interval
+    );
+
+    loop {
+        let video_files = discover_video_files(&settings.input_dir, &settings.include);
 
-    if input.trim().eq_ignore_ascii_case("Y") {
+        let mut stale_files = Vec::new();
         for video_file in video_files {
-            fs::remove_file(&video_file)?;
-            println!("Deleted file: {:?}", video_file);
+            if needs_processing(settings, &video_file)? {
+                stale_files.push(video_file);
+            }
         }
-        println!("Original files deleted.");
-    }
 
-    Ok(())
+        let processed = process_files(settings, &stale_files)?;
+        if !processed.is_empty() {
+            println!("Processed {} file(s) this cycle.", processed.len());
+        }
+        std::thread::sleep(std::time::Duration::from_secs(settings.interval));
+    }
 }
 
-fn main() -> io::Result<()> {
-    // Define and parse command-line arguments using clap
-    let matches = Command::new("Video Compressor")
+/// Builds the clap command line, shared by `main` (via `get_matches`) and
+/// the tests (via `get_matches_from`).
+fn build_cli() -> Command {
+    Command::new("Video Compressor")
         .version("1.0")
         .author("Eduardo Rezaghi <eduardo.rezaghi@gmail.com>")
-        .about("Compresses video files in a directory using ffmpeg with hevc_nvenc")
+        .about("Compresses video files in a directory using ffmpeg")
         .arg(
             Arg::new("input_directory")
                 .short('i')
                 .long("input")
                 .value_name("INPUT_DIRECTORY")
                 .help("Specifies the input directory containing video files")
-                .required(true)
                 .value_parser(clap::value_parser!(PathBuf)),
         )
         .arg(
@@ -117,26 +355,185 @@ fn main() -> io::Result<()> {
                 .long("output")
                 .value_name("OUTPUT_DIRECTORY")
                 .help("Specifies the output directory for the compressed videos")
-                .required(true)
                 .value_parser(clap::value_parser!(PathBuf)),
         )
-        .get_matches();
+        .arg(
+            Arg::new("preserve_structure")
+                .long("preserve-structure")
+                .help("Recreate the input directory tree under the output directory")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .help("Number of ffmpeg processes to run concurrently (default: number of CPUs)")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("Keep running, polling the input directory for new or changed files")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .help("Seconds to sleep between watch passes (default: 60)")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("encoder")
+                .short('e')
+                .long("encoder")
+                .value_name("ENCODER")
+                .help("Video encoder to use: hevc_nvenc, libx265, libx264, libsvtav1, av1_nvenc")
+                .value_parser(clap::value_parser!(Encoder)),
+        )
+        .arg(
+            Arg::new("start")
+                .long("start")
+                .value_name("HH:MM:SS")
+                .help("Seek to this timestamp before compressing each file"),
+        )
+        .arg(
+            Arg::new("duration")
+                .long("duration")
+                .value_name("HH:MM:SS")
+                .help("Only compress this much of each file, starting at --start"),
+        )
+        .arg(
+            Arg::new("fps")
+                .long("fps")
+                .value_name("N")
+                .help("Resample the output frame rate")
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            Arg::new("cleanup")
+                .long("cleanup")
+                .value_name("BEHAVIOR")
+                .help("What to do with originals after a successful compress: keep, delete, archive")
+                .value_parser(clap::value_parser!(CleanupBehavior)),
+        )
+        .arg(
+            Arg::new("archive_dir")
+                .long("archive-dir")
+                .value_name("ARCHIVE_DIRECTORY")
+                .help("Directory originals are moved into when --cleanup=archive")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("remove_empty_dirs")
+                .long("remove-empty-dirs")
+                .help("After --cleanup=delete, prune input subdirectories left empty")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .value_name("CONFIG_FILE")
+                .help("Path to a TOML config file; CLI flags override its values")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
 
-    // Extract input and output directories from the arguments
-    let input_directory = Path::new(matches.get_one::<PathBuf>("input_directory").unwrap());
-    let output_directory = Path::new(matches.get_one::<PathBuf>("output_directory").unwrap());
+fn main() -> io::Result<()> {
+    let matches = build_cli().get_matches();
 
-    compress_videos(input_directory, output_directory)?;
+    let config = match matches.get_one::<PathBuf>("config") {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
 
-    // Optionally delete original files
-    let video_files: Vec<PathBuf> = WalkDir::new(input_directory)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .map(|e| e.into_path())
-        .collect();
+    let mut settings = Settings::resolve(&matches, config)?;
+    settings.encoder = encoder::resolve_encoder(&settings.ffmpeg_binary, settings.encoder)?;
 
-    delete_original_files(video_files)?;
+    if settings.watch {
+        return watch(&settings);
+    }
+
+    run_once(&settings)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_flags_override_config_values() {
+        let matches = build_cli().get_matches_from([
+            "video-compressor",
+            "-i",
+            "cli-in",
+            "-o",
+            "cli-out",
+            "--encoder",
+            "libx264",
+        ]);
+        let mut config = Config::default();
+        config.files.input_path = Some(PathBuf::from("config-in"));
+        config.files.output_path = Some(PathBuf::from("config-out"));
+        config.ffmpeg.codec = Some("libx265".to_string());
+
+        let settings = Settings::resolve(&matches, config).unwrap();
+
+        assert_eq!(settings.input_dir, PathBuf::from("cli-in"));
+        assert_eq!(settings.output_dir, PathBuf::from("cli-out"));
+        assert_eq!(settings.encoder, Encoder::Libx264);
+    }
+
+    #[test]
+    fn config_values_used_when_cli_flags_absent() {
+        let matches = build_cli().get_matches_from(["video-compressor"]);
+        let mut config = Config::default();
+        config.files.input_path = Some(PathBuf::from("config-in"));
+        config.files.output_path = Some(PathBuf::from("config-out"));
+        config.ffmpeg.codec = Some("libx265".to_string());
+
+        let settings = Settings::resolve(&matches, config).unwrap();
+
+        assert_eq!(settings.input_dir, PathBuf::from("config-in"));
+        assert_eq!(settings.output_dir, PathBuf::from("config-out"));
+        assert_eq!(settings.encoder, Encoder::Libx265);
+    }
+
+    #[test]
+    fn missing_input_and_output_is_an_error() {
+        let matches = build_cli().get_matches_from(["video-compressor"]);
+        let config = Config::default();
+
+        assert!(Settings::resolve(&matches, config).is_err());
+    }
+
+    #[test]
+    fn cleanup_behavior_falls_back_to_config_then_default() {
+        let matches = build_cli().get_matches_from([
+            "video-compressor",
+            "-i",
+            "in",
+            "-o",
+            "out",
+        ]);
+        let mut config = Config::default();
+        config.files.cleanup.original_cleanup_behavior = Some("delete".to_string());
+
+        let settings = Settings::resolve(&matches, config).unwrap();
+        assert_eq!(settings.cleanup.behavior, CleanupBehavior::Delete);
+
+        let matches = build_cli().get_matches_from([
+            "video-compressor",
+            "-i",
+            "in",
+            "-o",
+            "out",
+        ]);
+        let settings = Settings::resolve(&matches, Config::default()).unwrap();
+        assert_eq!(settings.cleanup.behavior, CleanupBehavior::Keep);
+    }
+}